@@ -1,5 +1,10 @@
 use fst::automaton::Automaton;
-use pyo3::{prelude::*, types::PyType};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyType};
+use regex_automata::{
+    dfa::{dense::DFA, Automaton as _},
+    util::{primitives::StateID, start},
+    Anchored,
+};
 use std::sync::Arc;
 
 #[inline]
@@ -109,6 +114,57 @@ fn subsequence_accept(node: &[u8], state: usize, byte: u8) -> State {
     State::Subsequence(state + usize::from(byte == node[state]))
 }
 
+#[inline]
+fn levenshtein_start(query: &[u8]) -> State {
+    State::Levenshtein((0..=query.len() as u32).collect())
+}
+
+#[inline]
+fn levenshtein_is_match(row: &[u32], max_dist: u32) -> bool {
+    row.last().copied().unwrap_or(0) <= max_dist
+}
+
+#[inline]
+fn levenshtein_can_match(row: &[u32], max_dist: u32) -> bool {
+    row.iter().copied().min().unwrap_or(u32::MAX) <= max_dist
+}
+
+#[inline]
+fn levenshtein_accept(query: &[u8], max_dist: u32, row: &[u32], byte: u8) -> State {
+    let mut next = Vec::with_capacity(row.len());
+    next.push((row[0] + 1).min(max_dist + 1));
+    for i in 1..row.len() {
+        let cost = row[i - 1] + u32::from(query[i - 1] != byte);
+        let cost = cost.min(row[i] + 1).min(next[i - 1] + 1);
+        next.push(cost.min(max_dist + 1));
+    }
+    State::Levenshtein(next)
+}
+
+#[inline]
+fn regex_start(dfa: &DFA<Vec<u32>>) -> State {
+    let config = start::Config::new().anchored(Anchored::Yes);
+    let state = dfa
+        .start_state(&config)
+        .expect("anchored regex start state");
+    State::Regex(state)
+}
+
+#[inline]
+fn regex_is_match(dfa: &DFA<Vec<u32>>, state: StateID) -> bool {
+    dfa.is_match_state(dfa.next_eoi_state(state))
+}
+
+#[inline]
+fn regex_can_match(dfa: &DFA<Vec<u32>>, state: StateID) -> bool {
+    !dfa.is_dead_state(state)
+}
+
+#[inline]
+fn regex_accept(dfa: &DFA<Vec<u32>>, state: StateID, byte: u8) -> State {
+    State::Regex(dfa.next_state(state, byte))
+}
+
 #[derive(Debug)]
 pub enum StartsWithState {
     Done,
@@ -270,18 +326,45 @@ pub enum State {
     AlwaysMatch,
     Str(Option<usize>),
     Subsequence(usize),
+    Levenshtein(Vec<u32>),
+    Regex(StateID),
     StartsWith(Box<StartsWithState>),
     Complement(Box<ComplementState>),
     Intersection(Box<IntersectionState>),
     Union(Box<UnionState>),
 }
 
+impl State {
+    /// Expose this state as an opaque integer for callers that want to rank or
+    /// compare matches without recomputing whatever the automaton tracks
+    /// internally, e.g., for Levenshtein the edit distance between the key
+    /// and the query.
+    /// Returns None where a single integer wouldn't be meaningful.
+    pub fn opaque(&self) -> Option<i64> {
+        match self {
+            State::NeverMatch | State::AlwaysMatch => None,
+            State::Str(pos) => pos.map(|pos| pos as i64),
+            State::Subsequence(pos) => Some(*pos as i64),
+            State::Levenshtein(row) => row.last().copied().map(i64::from),
+            State::Regex(_) => None,
+            State::StartsWith(state) => match state.as_ref() {
+                StartsWithState::Done => Some(0),
+                StartsWithState::Running(inner) => inner.opaque(),
+            },
+            State::Complement(state) => state.0.opaque(),
+            State::Intersection(_) | State::Union(_) => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Node {
     NeverMatch,
     AlwaysMatch,
     Str(Vec<u8>),
     Subsequence(Vec<u8>),
+    Levenshtein(Vec<u8>, u32),
+    Regex(Arc<DFA<Vec<u32>>>),
     StartsWith(Arc<Node>),
     Complement(Arc<Node>),
     Intersection((Arc<Node>, Arc<Node>)),
@@ -297,6 +380,8 @@ impl Automaton for Node {
             Self::AlwaysMatch => alwaysmatch_start(),
             Self::Str(_) => str_start(),
             Self::Subsequence(_) => subsequence_start(),
+            Self::Levenshtein(query, _) => levenshtein_start(query),
+            Self::Regex(dfa) => regex_start(dfa),
             Self::StartsWith(n) => starts_with_start(n),
             Self::Complement(n) => complement_start(n),
             Self::Intersection(n) => intersection_start(n),
@@ -310,6 +395,10 @@ impl Automaton for Node {
             (Self::AlwaysMatch, State::AlwaysMatch) => alwaysmatch_is_match(),
             (Self::Str(n), State::Str(state)) => str_is_match(n, state),
             (Self::Subsequence(n), State::Subsequence(state)) => subsequence_is_match(n, *state),
+            (Self::Levenshtein(_, max_dist), State::Levenshtein(row)) => {
+                levenshtein_is_match(row, *max_dist)
+            }
+            (Self::Regex(dfa), State::Regex(state)) => regex_is_match(dfa, *state),
             (Self::StartsWith(_), State::StartsWith(state)) => starts_with_is_match(state),
             (Self::Complement(n), State::Complement(state)) => complement_is_match(n, state),
             (Self::Intersection(n), State::Intersection(state)) => intersection_is_match(n, state),
@@ -325,6 +414,10 @@ impl Automaton for Node {
             (Self::AlwaysMatch, State::AlwaysMatch) => alwaysmatch_can_match(),
             (Self::Str(_), State::Str(state)) => str_can_match(state),
             (Self::Subsequence(_), State::Subsequence(_)) => subsequence_can_match(),
+            (Self::Levenshtein(_, max_dist), State::Levenshtein(row)) => {
+                levenshtein_can_match(row, *max_dist)
+            }
+            (Self::Regex(dfa), State::Regex(state)) => regex_can_match(dfa, *state),
             (Self::StartsWith(n), State::StartsWith(state)) => starts_with_can_match(n, state),
             (Self::Complement(n), State::Complement(state)) => complement_can_match(n, state),
             (Self::Intersection(n), State::Intersection(state)) => intersection_can_match(n, state),
@@ -342,6 +435,8 @@ impl Automaton for Node {
             (Self::Subsequence(n), State::Subsequence(state)) => {
                 subsequence_will_always_match(n, *state)
             }
+            (Self::Levenshtein(_, _), State::Levenshtein(_)) => false,
+            (Self::Regex(_), State::Regex(_)) => false,
             (Self::StartsWith(_), State::StartsWith(state)) => starts_with_will_always_match(state),
             (Self::Complement(n), State::Complement(state)) => {
                 complement_will_always_match(n, state)
@@ -362,6 +457,10 @@ impl Automaton for Node {
             (Self::Subsequence(n), State::Subsequence(state)) => {
                 subsequence_accept(n, *state, byte)
             }
+            (Self::Levenshtein(query, max_dist), State::Levenshtein(row)) => {
+                levenshtein_accept(query, *max_dist, row, byte)
+            }
+            (Self::Regex(dfa), State::Regex(state)) => regex_accept(dfa, *state, byte),
             (Self::StartsWith(n), State::StartsWith(state)) => starts_with_accept(n, state, byte),
             (Self::Complement(n), State::Complement(state)) => complement_accept(n, state, byte),
             (Self::Intersection(n), State::Intersection(state)) => {
@@ -416,7 +515,7 @@ impl Automaton for ArcNode {
 /// Automata can be used to efficiently apply complex search patterns
 /// to the keys of maps and sets.
 /// Use one of the classmethods never, always, str,
-/// or subsequence to create a new automaton.
+/// subsequence, levenshtein, or regex to create a new automaton.
 /// Add more complex behavior on top with starts_with, complement,
 /// intersection, or union.
 /// E.g., an automaton that matches keys that start with b"foo" or b"bar":
@@ -434,6 +533,13 @@ impl AutomatonGraph {
     pub fn get(&self) -> ArcNode {
         ArcNode(self.root.clone())
     }
+
+    /// Wrap a `Node` built from Rust, without going through a Python classmethod.
+    pub(crate) fn from_node(root: Node) -> Self {
+        Self {
+            root: Arc::new(root),
+        }
+    }
 }
 
 #[pymethods]
@@ -471,6 +577,35 @@ impl AutomatonGraph {
         }
     }
 
+    /// Create a new Automaton that matches any key within Levenshtein
+    /// (edit) distance max_dist of query, i.e., keys reachable from query
+    /// by at most max_dist single-byte insertions, deletions, or substitutions.
+    #[classmethod]
+    fn levenshtein(_cls: &Bound<'_, PyType>, query: &[u8], max_dist: u32) -> Self {
+        Self {
+            root: Arc::new(Node::Levenshtein(query.to_owned(), max_dist)),
+        }
+    }
+
+    /// Create a new Automaton that matches keys that, taken as a whole,
+    /// match the given regular expression pattern (str or bytes).
+    /// Raises ValueError if pattern fails to compile.
+    #[classmethod]
+    fn regex(_cls: &Bound<'_, PyType>, pattern: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let pattern = if let Ok(pattern) = pattern.extract::<&str>() {
+            pattern.to_owned()
+        } else {
+            let pattern = pattern.extract::<&[u8]>()?;
+            String::from_utf8(pattern.to_owned())
+                .map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?
+        };
+        let dfa =
+            DFA::new(&pattern).map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?;
+        Ok(Self {
+            root: Arc::new(Node::Regex(Arc::new(dfa))),
+        })
+    }
+
     /// Modify this automaton to match any key that starts with a prefix that previously matched,
     /// e.g., if self matched b"abc", it will now match b"abcde".
     /// Returns self to allow chaining with other methods.
@@ -505,3 +640,53 @@ impl AutomatonGraph {
         slf
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(query: &[u8], max_dist: u32, key: &[u8]) -> State {
+        let node = Node::Levenshtein(query.to_vec(), max_dist);
+        let mut state = node.start();
+        for &byte in key {
+            state = node.accept(&state, byte);
+        }
+        state
+    }
+
+    #[test]
+    fn levenshtein_matches_within_max_dist() {
+        let node = Node::Levenshtein(b"abc".to_vec(), 2);
+        assert!(node.is_match(&run(b"abc", 2, b"abc")));
+        // one substitution away
+        assert!(node.is_match(&run(b"abc", 2, b"abd")));
+    }
+
+    #[test]
+    fn levenshtein_rejects_beyond_max_dist() {
+        let node = Node::Levenshtein(b"abc".to_vec(), 1);
+        let state = run(b"abc", 1, b"xyz");
+        assert!(!node.is_match(&state));
+        assert!(!node.can_match(&state));
+    }
+
+    #[test]
+    fn levenshtein_opaque_is_true_edit_distance_not_best_prefix() {
+        let state = run(b"abc", 2, b"abc");
+        assert_eq!(state.opaque(), Some(0));
+
+        // "ab" shares a zero-edit-distance prefix with "abcdef", but as a whole
+        // match it costs 4 edits (4 trailing deletions).
+        let state = run(b"abcdef", 4, b"ab");
+        assert!(Node::Levenshtein(b"abcdef".to_vec(), 4).is_match(&state));
+        assert_eq!(state.opaque(), Some(4));
+    }
+
+    #[test]
+    fn regex_opaque_state_is_not_meaningful_for_ranking() {
+        let dfa = DFA::new("a+").unwrap();
+        let node = Node::Regex(Arc::new(dfa));
+        let state = node.start();
+        assert_eq!(state.opaque(), None);
+    }
+}
@@ -1,3 +1,4 @@
+use memmap2::Mmap;
 use pyo3::{
     buffer::{Element, PyBuffer},
     exceptions::PyBufferError,
@@ -144,3 +145,22 @@ impl<T: Element> AsRef<[T]> for PyBufferRef<T> {
         }
     }
 }
+
+/// Backing storage for a `Map` or `Set`: a Python buffer-protocol object
+/// (see `PyBufferRef`), a read-only memory map opened directly from a path,
+/// or bytes already owned on the Rust side, e.g., freshly built in memory.
+pub enum ByteSource {
+    Buffer(PyBufferRef<u8>),
+    Mmap(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl AsRef<[u8]> for ByteSource {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            ByteSource::Buffer(buf) => buf.as_ref(),
+            ByteSource::Mmap(mmap) => mmap.as_ref(),
+            ByteSource::Owned(buf) => buf.as_slice(),
+        }
+    }
+}
@@ -11,6 +11,7 @@ use pyo3::{
     prelude::*,
     types::{PyTuple, PyType},
 };
+use regex_automata::dfa::dense::DFA;
 use std::{
     borrow::Cow,
     fs,
@@ -19,10 +20,10 @@ use std::{
     sync::Arc,
 };
 
-use crate::automaton::{ArcNode, AutomatonGraph};
-use crate::buffer::{Buffer, PyBufferRef};
+use crate::automaton::{ArcNode, AutomatonGraph, Node};
+use crate::buffer::{Buffer, ByteSource, PyBufferRef};
 
-type PyMap = fst::Map<PyBufferRef<u8>>;
+type PyMap = fst::Map<ByteSource>;
 
 type ItemStream<'f> = Box<dyn for<'a> Streamer<'a, Item = (&'a [u8], u64)> + Send + 'f>;
 type KeyStream<'f> = Box<dyn for<'a> Streamer<'a, Item = &'a [u8]> + Send + 'f>;
@@ -115,6 +116,41 @@ impl AutomatonIterator {
     }
 }
 
+type OpStream<'f> = Box<dyn for<'a> Streamer<'a, Item = OpItem<'a>> + Send + 'f>;
+
+#[pyclass(name = "MapOpIterator")]
+#[self_referencing]
+struct OpIterator {
+    maps: Vec<Arc<PyMap>>,
+    select: Select,
+    #[borrows(maps)]
+    #[not_covariant]
+    stream: OpStream<'this>,
+}
+
+#[pymethods]
+impl OpIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<(Cow<[u8]>, u64)>> {
+        let select = self.with_select(Select::clone);
+        let item = self.with_stream_mut(|stream| {
+            stream
+                .next()
+                .map(|(key, posval)| (key.to_vec(), posval.to_vec()))
+        });
+        match item {
+            Some((key, posval)) => Ok(Some((
+                Cow::from(key),
+                select_value(py, &select, &posval)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
 const BUFSIZE: usize = 4 * 1024 * 1024;
 
 fn add_range<'m, A: Automaton>(
@@ -146,16 +182,15 @@ where
     W: io::Write,
     S: 'f + for<'a> Streamer<'a, Item = OpItem<'a>>,
     I: for<'a> IntoStreamer<'a, Into = S, Item = OpItem<'a>>,
-    F: Fn(&[IndexedValue]) -> u64,
+    F: Fn(&[IndexedValue]) -> PyResult<u64>,
 {
     let mut stream = stream.into_stream();
     let mut builder =
         MapBuilder::new(buf).map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))?;
     while let Some((key, posval)) = stream.next() {
-        // TODO other options instead of last value
         // unwrap() is OK here, since stream.next() never returns an empty slice
         builder
-            .insert(key, select(posval))
+            .insert(key, select(posval)?)
             .map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?;
     }
     builder
@@ -163,24 +198,40 @@ where
         .map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))
 }
 
+/// A path alongside `path` to stage a build into, so a `select` callable that
+/// raises or misbehaves partway through the merge can't leave `path` itself
+/// truncated. Swapped into place with `fs::rename` only once the build
+/// finishes without error.
+fn tmp_path_for(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".tmp.{}", std::process::id()));
+    PathBuf::from(tmp)
+}
+
 fn build_from_stream<'f, I, S, F>(path: &Path, stream: I, select: F) -> PyResult<Option<Buffer>>
 where
     S: 'f + for<'a> Streamer<'a, Item = OpItem<'a>>,
     I: for<'a> IntoStreamer<'a, Into = S, Item = OpItem<'a>>,
-    F: Fn(&[IndexedValue]) -> u64,
+    F: Fn(&[IndexedValue]) -> PyResult<u64>,
 {
     if path == Path::new(":memory:") {
         let buf = Vec::with_capacity(10 * (1 << 10));
         let buf = fill_from_stream(stream, select, buf)?;
         Ok(Some(Buffer::new(buf)))
     } else {
+        let tmp_path = tmp_path_for(path);
         let wp = fs::OpenOptions::new()
             .create(true)
             .truncate(true)
             .write(true)
-            .open(path)?;
+            .open(&tmp_path)?;
         let writer = BufWriter::with_capacity(BUFSIZE, wp);
-        fill_from_stream(stream, select, writer)?;
+        let result = fill_from_stream(stream, select, writer);
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+            result?;
+        }
+        fs::rename(&tmp_path, path)?;
         Ok(None)
     }
 }
@@ -230,6 +281,20 @@ fn fill_from_iterable<W: io::Write>(iterable: &Bound<'_, PyAny>, buf: W) -> PyRe
         .map_err(|err| PyErr::new::<PyIOError, _>(err.to_string()))
 }
 
+/// Memory-map the file at `path` and open it as a map. The returned `PyMap`
+/// owns the `Mmap`, so it stays valid after the `File` used to create it
+/// (and this function's stack frame) goes away.
+fn map_from_path(path: &Path) -> PyResult<Arc<PyMap>> {
+    let file = fs::File::open(path)?;
+    // Safety: we have to assume the file is not concurrently modified or
+    // truncated for the lifetime of the mapping, as is inherent to mmap.
+    let mmap = unsafe { memmap2::Mmap::map(&file) }?;
+    Ok(Arc::new(
+        fst::Map::new(ByteSource::Mmap(mmap))
+            .map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))?,
+    ))
+}
+
 fn mapvec(first: &Map, tuple: &Bound<'_, PyTuple>) -> PyResult<Vec<Arc<PyMap>>> {
     let py = tuple.py();
     let mut maps: Vec<Arc<PyMap>> = Vec::with_capacity(tuple.len());
@@ -273,8 +338,41 @@ pub enum Op {
     Median,
 }
 
+/// How conflicting values for the same key are merged in a set operation:
+/// either one of the builtin Op strategies, or a Python callable that receives
+/// the list of colliding values and returns the merged value.
+#[derive(Clone)]
+pub enum Select {
+    Op(Op),
+    Callable(Py<PyAny>),
+}
+
+impl<'py> FromPyObject<'py> for Select {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(op) = ob.extract::<Op>() {
+            Ok(Select::Op(op))
+        } else if ob.is_callable() {
+            Ok(Select::Callable(ob.clone().unbind()))
+        } else {
+            Err(PyErr::new::<PyTypeError, _>(
+                "select must be an Op or a callable taking a list of values",
+            ))
+        }
+    }
+}
+
+fn select_value(py: Python<'_>, select: &Select, posval: &[IndexedValue]) -> PyResult<u64> {
+    match select {
+        Select::Op(op) => Ok(select_op_value(op.clone(), posval)),
+        Select::Callable(callable) => {
+            let values: Vec<u64> = posval.iter().map(|i| i.value).collect();
+            callable.bind(py).call1((values,))?.extract()
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
-fn select_value(sf: Op, posval: &[IndexedValue]) -> u64 {
+fn select_op_value(sf: Op, posval: &[IndexedValue]) -> u64 {
     match sf {
         Op::First => posval.first().unwrap().value,
         Op::Mid => posval[posval.len() / 2].value,
@@ -303,7 +401,8 @@ fn select_value(sf: Op, posval: &[IndexedValue]) -> u64 {
 ///
 /// data can be any object that supports the buffer protocol,
 /// e.g., Buffer, bytes, memoryview, mmap, etc.
-/// Use Map.build to create suitable data.
+/// Use Map.build to create suitable data, or Map.open to memory-map
+/// a map directly from a path without loading it into memory.
 ///
 /// Important: data needs to be contiguous.
 ///
@@ -352,13 +451,26 @@ impl Map {
     #[new]
     fn init(data: &Bound<'_, PyAny>) -> PyResult<Map> {
         let view: PyBuffer<u8> = PyBuffer::get_bound(data)?;
-        let slice = PyBufferRef::new(view)?;
+        let slice = ByteSource::Buffer(PyBufferRef::new(view)?);
         let inner = Arc::new(
             fst::Map::new(slice).map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))?,
         );
         Ok(Self { inner })
     }
 
+    /// Open a map from a file at the given path, memory-mapping its contents
+    /// read-only instead of loading them into memory.
+    /// path can be str or Path.
+    ///
+    /// This allows querying maps much larger than available memory, since
+    /// pages are only faulted in as they're touched during a search.
+    #[classmethod]
+    fn open(_cls: &Bound<'_, PyType>, path: PathBuf) -> PyResult<Map> {
+        Ok(Self {
+            inner: map_from_path(&path)?,
+        })
+    }
+
     /// Since maps are immutable, returns self.
     fn copy(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
         slf
@@ -567,6 +679,72 @@ impl Map {
         .build()
     }
 
+    /// Iterate over all key-value items whose key is within Levenshtein (edit)
+    /// distance max_dist of query, i.e., keys reachable from query by at most
+    /// max_dist single-byte insertions, deletions, or substitutions.
+    /// Optionally apply range limits
+    /// ge (greater than or equal),
+    /// gt (greater than),
+    /// le (less than or equal),
+    /// and lt (less than).
+    #[pyo3(signature = (query, max_dist, ge=None, gt=None, le=None, lt=None))]
+    fn fuzzy(
+        &self,
+        query: Vec<u8>,
+        max_dist: u32,
+        ge: Option<&[u8]>,
+        gt: Option<&[u8]>,
+        le: Option<&[u8]>,
+        lt: Option<&[u8]>,
+    ) -> AutomatonIterator {
+        let automaton = AutomatonGraph::from_node(Node::Levenshtein(query, max_dist));
+        AutomatonIteratorBuilder {
+            map: self.inner.clone(),
+            automaton: automaton.get(),
+            stream_builder: |map, automaton| {
+                add_range(map.search(automaton.get()), ge, gt, le, lt).into_stream()
+            },
+        }
+        .build()
+    }
+
+    /// Iterate over all key-value items whose key, taken as a whole,
+    /// matches the given regular expression pattern (str or bytes).
+    /// Raises ValueError if pattern fails to compile.
+    /// Optionally apply range limits
+    /// ge (greater than or equal),
+    /// gt (greater than),
+    /// le (less than or equal),
+    /// and lt (less than).
+    #[pyo3(signature = (pattern, ge=None, gt=None, le=None, lt=None))]
+    fn regex(
+        &self,
+        pattern: &Bound<'_, PyAny>,
+        ge: Option<&[u8]>,
+        gt: Option<&[u8]>,
+        le: Option<&[u8]>,
+        lt: Option<&[u8]>,
+    ) -> PyResult<AutomatonIterator> {
+        let pattern = if let Ok(pattern) = pattern.extract::<&str>() {
+            pattern.to_owned()
+        } else {
+            let pattern = pattern.extract::<&[u8]>()?;
+            String::from_utf8(pattern.to_owned())
+                .map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?
+        };
+        let dfa =
+            DFA::new(&pattern).map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?;
+        let automaton = AutomatonGraph::from_node(Node::Regex(Arc::new(dfa)));
+        Ok(AutomatonIteratorBuilder {
+            map: self.inner.clone(),
+            automaton: automaton.get(),
+            stream_builder: |map, automaton| {
+                add_range(map.search(automaton.get()), ge, gt, le, lt).into_stream()
+            },
+        }
+        .build())
+    }
+
     /// Iterate over all key-value items with optional range limits for the key
     /// ge (greater than or equal),
     /// gt (greater than),
@@ -592,41 +770,45 @@ impl Map {
     /// Build a new map that is the union of self and others.
     /// others must be instances of Map.
     /// select specifies how conflicts are resolved if keys are
-    /// present more than once.
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
     /// If path is ":memory:", returns a Buffer containing the map data
     /// instead of writing to path.
     /// path can be str or Path.
-    #[pyo3(signature = (path, *others, select=Op::Last))]
+    #[pyo3(signature = (path, *others, select=Select::Op(Op::Last)))]
     #[allow(clippy::needless_pass_by_value)]
     fn union(
         &self,
+        py: Python<'_>,
         path: PathBuf,
         others: &Bound<'_, PyTuple>,
-        select: Op,
+        select: Select,
     ) -> PyResult<Option<Buffer>> {
         let maps = mapvec(self, others)?;
         let stream = opbuilder(&maps).union();
-        build_from_stream(&path, stream, |posval| select_value(select.clone(), posval))
+        build_from_stream(&path, stream, |posval| select_value(py, &select, posval))
     }
 
     /// Build a new map that is the intersection of self and others.
     /// others must be instances of Map.
     /// select specifies how conflicts are resolved if keys are
-    /// present more than once.
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
     /// If path is ":memory:", returns a Buffer containing the map data
     /// instead of writing to path.
     /// path can be str or Path.
-    #[pyo3(signature = (path, *others, select=Op::Last))]
+    #[pyo3(signature = (path, *others, select=Select::Op(Op::Last)))]
     #[allow(clippy::needless_pass_by_value)]
     fn intersection(
         &self,
+        py: Python<'_>,
         path: PathBuf,
         others: &Bound<'_, PyTuple>,
-        select: Op,
+        select: Select,
     ) -> PyResult<Option<Buffer>> {
         let maps = mapvec(self, others)?;
         let stream = opbuilder(&maps).intersection();
-        build_from_stream(&path, stream, |posval| select_value(select.clone(), posval))
+        build_from_stream(&path, stream, |posval| select_value(py, &select, posval))
     }
 
     /// Build a new map that is the difference between self and all others,
@@ -634,21 +816,23 @@ impl Map {
     /// but not in others.
     /// others must be instances of Map.
     /// select specifies how conflicts are resolved if keys are
-    /// present more than once.
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
     /// If path is ":memory:", returns a Buffer containing the map data
     /// instead of writing to path.
     /// path can be str or Path.
-    #[pyo3(signature = (path, *others, select=Op::Last))]
+    #[pyo3(signature = (path, *others, select=Select::Op(Op::Last)))]
     #[allow(clippy::needless_pass_by_value)]
     fn difference(
         &self,
+        py: Python<'_>,
         path: PathBuf,
         others: &Bound<'_, PyTuple>,
-        select: Op,
+        select: Select,
     ) -> PyResult<Option<Buffer>> {
         let maps = mapvec(self, others)?;
         let stream = opbuilder(&maps).difference();
-        build_from_stream(&path, stream, |posval| select_value(select.clone(), posval))
+        build_from_stream(&path, stream, |posval| select_value(py, &select, posval))
     }
 
     /// Build a new map that is the symmetric difference between self and others.
@@ -657,20 +841,205 @@ impl Map {
     /// self or others, but not in both.
     /// others must be instances of Map.
     /// select specifies how conflicts are resolved if keys are
-    /// present more than once.
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
     /// If path is ":memory:", returns a Buffer containing the map data
     /// instead of writing to path.
     /// path can be str or Path.
-    #[pyo3(signature = (path, *others, select=Op::Last))]
+    #[pyo3(signature = (path, *others, select=Select::Op(Op::Last)))]
     #[allow(clippy::needless_pass_by_value)]
     fn symmetric_difference(
         &self,
+        py: Python<'_>,
         path: PathBuf,
         others: &Bound<'_, PyTuple>,
-        select: Op,
+        select: Select,
     ) -> PyResult<Option<Buffer>> {
         let maps = mapvec(self, others)?;
         let stream = opbuilder(&maps).symmetric_difference();
-        build_from_stream(&path, stream, |posval| select_value(select.clone(), posval))
+        build_from_stream(&path, stream, |posval| select_value(py, &select, posval))
+    }
+
+    /// Lazily iterate over the union of self and others, without building a new map.
+    /// others must be instances of Map.
+    /// select specifies how conflicts are resolved if keys are
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
+    #[pyo3(signature = (*others, select=Select::Op(Op::Last)))]
+    #[allow(clippy::needless_pass_by_value)]
+    fn union_iter(&self, others: &Bound<'_, PyTuple>, select: Select) -> PyResult<OpIterator> {
+        let maps = mapvec(self, others)?;
+        Ok(OpIteratorBuilder {
+            maps,
+            select,
+            stream_builder: |maps| Box::new(opbuilder(maps).union()),
+        }
+        .build())
+    }
+
+    /// Lazily iterate over the intersection of self and others, without building a new map.
+    /// others must be instances of Map.
+    /// select specifies how conflicts are resolved if keys are
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
+    #[pyo3(signature = (*others, select=Select::Op(Op::Last)))]
+    #[allow(clippy::needless_pass_by_value)]
+    fn intersection_iter(
+        &self,
+        others: &Bound<'_, PyTuple>,
+        select: Select,
+    ) -> PyResult<OpIterator> {
+        let maps = mapvec(self, others)?;
+        Ok(OpIteratorBuilder {
+            maps,
+            select,
+            stream_builder: |maps| Box::new(opbuilder(maps).intersection()),
+        }
+        .build())
+    }
+
+    /// Lazily iterate over the difference between self and all others,
+    /// without building a new map.
+    /// others must be instances of Map.
+    /// select specifies how conflicts are resolved if keys are
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
+    #[pyo3(signature = (*others, select=Select::Op(Op::Last)))]
+    #[allow(clippy::needless_pass_by_value)]
+    fn difference_iter(
+        &self,
+        others: &Bound<'_, PyTuple>,
+        select: Select,
+    ) -> PyResult<OpIterator> {
+        let maps = mapvec(self, others)?;
+        Ok(OpIteratorBuilder {
+            maps,
+            select,
+            stream_builder: |maps| Box::new(opbuilder(maps).difference()),
+        }
+        .build())
+    }
+
+    /// Lazily iterate over the symmetric difference between self and others,
+    /// without building a new map.
+    /// others must be instances of Map.
+    /// select specifies how conflicts are resolved if keys are
+    /// present more than once: either one of the Op variants, or a callable
+    /// that takes the list of colliding values and returns the merged value.
+    #[pyo3(signature = (*others, select=Select::Op(Op::Last)))]
+    #[allow(clippy::needless_pass_by_value)]
+    fn symmetric_difference_iter(
+        &self,
+        others: &Bound<'_, PyTuple>,
+        select: Select,
+    ) -> PyResult<OpIterator> {
+        let maps = mapvec(self, others)?;
+        Ok(OpIteratorBuilder {
+            maps,
+            select,
+            stream_builder: |maps| Box::new(opbuilder(maps).symmetric_difference()),
+        }
+        .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn map_of(pairs: &[(&[u8], u64)]) -> Arc<PyMap> {
+        let mut builder = MapBuilder::new(Vec::new()).unwrap();
+        for (key, val) in pairs {
+            builder.insert(key, *val).unwrap();
+        }
+        let buf = builder.into_inner().unwrap();
+        Arc::new(fst::Map::new(ByteSource::Owned(buf)).unwrap())
+    }
+
+    #[test]
+    fn build_from_stream_leaves_target_file_untouched_when_select_fails() {
+        let maps = vec![
+            map_of(&[(b"a", 1), (b"b", 2)]),
+            map_of(&[(b"a", 10), (b"c", 3)]),
+        ];
+        let stream = opbuilder(&maps).union();
+
+        let path = std::env::temp_dir().join(format!(
+            "ducer-test-{}-{}.map",
+            std::process::id(),
+            line!()
+        ));
+        fs::write(&path, b"original contents").unwrap();
+
+        // Misbehaves on the second key, same as a buggy or aborting Python callable.
+        let calls = Cell::new(0u32);
+        let result = build_from_stream(&path, stream, |_posval| {
+            calls.set(calls.get() + 1);
+            if calls.get() == 2 {
+                Err(PyErr::new::<PyRuntimeError, _>("boom"))
+            } else {
+                Ok(0)
+            }
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original contents");
+        assert!(!tmp_path_for(&path).exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn map_from_path_stays_valid_after_the_opening_file_handle_is_dropped() {
+        let mut builder = MapBuilder::new(Vec::new()).unwrap();
+        builder.insert(b"a", 1).unwrap();
+        builder.insert(b"b", 2).unwrap();
+        let buf = builder.into_inner().unwrap();
+
+        let path = std::env::temp_dir().join(format!(
+            "ducer-test-{}-{}.map",
+            std::process::id(),
+            line!()
+        ));
+        fs::write(&path, &buf).unwrap();
+
+        // map_from_path's `File` is dropped before returning; the mmap must
+        // not depend on it staying open.
+        let map = map_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(map.get(b"a"), Some(1));
+        assert_eq!(map.get(b"b"), Some(2));
+        assert_eq!(map.get(b"c"), None);
+    }
+
+    #[test]
+    fn op_iterator_streams_the_union_applying_select_lazily() {
+        let maps = vec![
+            map_of(&[(b"a", 1), (b"b", 2)]),
+            map_of(&[(b"a", 10), (b"c", 3)]),
+        ];
+        let mut iter = OpIteratorBuilder {
+            maps,
+            select: Select::Op(Op::Last),
+            stream_builder: |maps| Box::new(opbuilder(maps).union()),
+        }
+        .build();
+
+        Python::with_gil(|py| {
+            let mut items = Vec::new();
+            while let Some(item) = iter.__next__(py).unwrap() {
+                items.push(item);
+            }
+            assert_eq!(
+                items,
+                vec![
+                    (Cow::from(b"a".to_vec()), 10),
+                    (Cow::from(b"b".to_vec()), 2),
+                    (Cow::from(b"c".to_vec()), 3),
+                ]
+            );
+        });
     }
 }
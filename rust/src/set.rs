@@ -1,6 +1,6 @@
 use fst::{
     automaton::{Automaton, Str, Subsequence},
-    set::{OpBuilder, Stream, StreamBuilder},
+    set::{OpBuilder, Stream, StreamBuilder, StreamWithState},
     IntoStreamer, SetBuilder, Streamer,
 };
 use ouroboros::self_referencing;
@@ -19,13 +19,13 @@ use std::{
 };
 
 use crate::{
-    automaton::{ArcNode, AutomatonGraph},
-    buffer::{Buffer, PyBufferRef},
+    automaton::{ArcNode, AutomatonGraph, Node},
+    buffer::{Buffer, ByteSource, PyBufferRef},
 };
 
 const BUFSIZE: usize = 4 * 1024 * 1024;
 
-type PySet = fst::Set<PyBufferRef<u8>>;
+type PySet = fst::Set<ByteSource>;
 
 type KeyStream<'f> = Box<dyn for<'a> Streamer<'a, Item = &'a [u8]> + Send + 'f>;
 
@@ -73,6 +73,28 @@ impl AutomatonIterator {
     }
 }
 
+#[pyclass(name = "SetAutomatonStateIterator")]
+#[self_referencing]
+struct AutomatonStateIterator {
+    set: Arc<PySet>,
+    automaton: ArcNode,
+    #[borrows(set, automaton)]
+    #[not_covariant]
+    stream: StreamWithState<'this, ArcNode>,
+}
+
+#[pymethods]
+impl AutomatonStateIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<(Cow<[u8]>, Option<i64>)> {
+        self.with_stream_mut(|stream| stream.next())
+            .map(|(key, state)| (Cow::from(key.to_vec()), state.opaque()))
+    }
+}
+
 fn add_range<'m, A: Automaton>(
     mut builder: StreamBuilder<'m, A>,
     ge: Option<&[u8]>,
@@ -118,15 +140,16 @@ where
         .map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))
 }
 
-fn build_from_stream<'f, I, S>(path: &Path, stream: I) -> PyResult<Option<Buffer>>
+/// Write `stream` to `path`, or, if `path` is ":memory:", build a Set
+/// directly from it instead of a `Buffer` the caller would have to
+/// re-parse, same as the dunder operators below.
+fn build_from_stream<'f, I, S>(py: Python<'_>, path: &Path, stream: I) -> PyResult<PyObject>
 where
     S: 'f + for<'a> Streamer<'a, Item = OpItem<'a>>,
     I: for<'a> IntoStreamer<'a, Into = S, Item = OpItem<'a>>,
 {
     if path == Path::new(":memory:") {
-        let buf = Vec::with_capacity(10 * (1 << 10));
-        let buf = fill_from_stream(stream, buf)?;
-        Ok(Some(Buffer::new(buf)))
+        Ok(set_from_stream(stream)?.into_py(py))
     } else {
         let wp = fs::OpenOptions::new()
             .create(true)
@@ -135,7 +158,72 @@ where
             .open(path)?;
         let writer = BufWriter::with_capacity(BUFSIZE, wp);
         fill_from_stream(stream, writer)?;
-        Ok(None)
+        Ok(py.None())
+    }
+}
+
+/// Write the keys present in at least `threshold` of `sets` to `buf`, via an
+/// explicit k-way merge of their streams. Since every input stream is sorted
+/// and deduplicated, the merged keys come out in strictly increasing order,
+/// so the result can be written directly without buffering it in memory.
+fn fill_quorum<W: io::Write>(sets: &[Arc<PySet>], threshold: usize, buf: W) -> PyResult<W> {
+    let mut streams: Vec<_> = sets.iter().map(|set| set.stream()).collect();
+    let mut heads: Vec<Option<Vec<u8>>> = streams
+        .iter_mut()
+        .map(|stream| stream.next().map(<[u8]>::to_vec))
+        .collect();
+    let mut builder =
+        SetBuilder::new(buf).map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))?;
+    loop {
+        let Some(min) = heads.iter().flatten().min().cloned() else {
+            break;
+        };
+        let mut count = 0usize;
+        for (head, stream) in heads.iter_mut().zip(streams.iter_mut()) {
+            if head.as_deref() == Some(min.as_slice()) {
+                count += 1;
+                *head = stream.next().map(<[u8]>::to_vec);
+            }
+        }
+        if count >= threshold {
+            builder
+                .insert(&min)
+                .map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?;
+        }
+    }
+    builder
+        .into_inner()
+        .map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))
+}
+
+/// Run `fill_quorum` into an in-memory set directly, same as `set_from_stream`
+/// does for the other set operations.
+fn set_from_quorum(sets: &[Arc<PySet>], threshold: usize) -> PyResult<Set> {
+    let buf = fill_quorum(sets, threshold, Vec::with_capacity(10 * (1 << 10)))?;
+    let inner = Arc::new(
+        fst::Set::new(ByteSource::Owned(buf))
+            .map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))?,
+    );
+    Ok(Set { inner })
+}
+
+fn build_quorum(
+    py: Python<'_>,
+    path: &Path,
+    sets: &[Arc<PySet>],
+    threshold: usize,
+) -> PyResult<PyObject> {
+    if path == Path::new(":memory:") {
+        Ok(set_from_quorum(sets, threshold)?.into_py(py))
+    } else {
+        let wp = fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path)?;
+        let writer = BufWriter::with_capacity(BUFSIZE, wp);
+        fill_quorum(sets, threshold, writer)?;
+        Ok(py.None())
     }
 }
 
@@ -176,6 +264,21 @@ fn opbuilder(sets: &Vec<Arc<PySet>>) -> OpBuilder {
     builder
 }
 
+/// Run stream into an in-memory set directly, instead of a `Buffer` that
+/// the caller would have to re-parse into a `Set`.
+fn set_from_stream<'f, I, S>(stream: I) -> PyResult<Set>
+where
+    S: 'f + for<'a> Streamer<'a, Item = OpItem<'a>>,
+    I: for<'a> IntoStreamer<'a, Into = S, Item = OpItem<'a>>,
+{
+    let buf = fill_from_stream(stream, Vec::with_capacity(10 * (1 << 10)))?;
+    let inner = Arc::new(
+        fst::Set::new(ByteSource::Owned(buf))
+            .map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))?,
+    );
+    Ok(Set { inner })
+}
+
 /// An immutable set of bytes keys, based on finite-state-transducers.
 /// Typically uses a fraction of the memory as the builtin set and can be streamed from a file.
 ///
@@ -200,6 +303,10 @@ fn opbuilder(sets: &Vec<Arc<PySet>>) -> OpBuilder {
 ///     s.issuperset(o)
 ///     s >= o  # superset
 ///     s > o  # proper superset
+///     s | o  # union, returns a new, in-memory Set
+///     s & o  # intersection, returns a new, in-memory Set
+///     s - o  # difference, returns a new, in-memory Set
+///     s ^ o  # symmetric difference, returns a new, in-memory Set
 ///
 /// Since sets are immutable, the following are **not implemented**:
 ///
@@ -213,9 +320,10 @@ fn opbuilder(sets: &Vec<Arc<PySet>>) -> OpBuilder {
 /// - symmetric_difference_update, ^=
 /// - update, |=
 ///
-/// Further, the |, &, -, ^ operators are also not implemented,
-/// since it is not possible to specify the storage path.
-/// Use Set.union, Set.intersection, Set.difference, and Set.symmetric_difference instead.
+/// The |, &, -, ^ operators always build the result in memory and only take
+/// one other Set. Use Set.union, Set.intersection, Set.difference, and
+/// Set.symmetric_difference to combine more than two sets at once or to
+/// write the result to a path.
 #[pyclass(sequence, subclass)]
 pub struct Set {
     inner: Arc<PySet>,
@@ -231,7 +339,7 @@ impl Set {
     #[new]
     fn init(data: &Bound<'_, PyAny>) -> PyResult<Set> {
         let view: PyBuffer<u8> = PyBuffer::get_bound(data)?;
-        let slice = PyBufferRef::new(view)?;
+        let slice = ByteSource::Buffer(PyBufferRef::new(view)?);
         let inner = Arc::new(
             fst::Set::new(slice).map_err(|err| PyErr::new::<PyRuntimeError, _>(err.to_string()))?,
         );
@@ -362,32 +470,52 @@ impl Set {
     /// gt (greater than),
     /// le (less than or equal),
     /// and lt (less than).
-    #[pyo3(signature = (str, ge=None, gt=None, le=None, lt=None))]
+    /// If with_state is True, yields (key, state) pairs instead of just key,
+    /// see search_with_state for what state means.
+    #[pyo3(signature = (str, ge=None, gt=None, le=None, lt=None, with_state=false))]
+    #[allow(clippy::too_many_arguments)]
     fn starts_with(
         &self,
+        py: Python<'_>,
         str: Vec<u8>,
         ge: Option<&[u8]>,
         gt: Option<&[u8]>,
         le: Option<&[u8]>,
         lt: Option<&[u8]>,
-    ) -> KeyIterator {
-        KeyIteratorBuilder {
-            set: self.inner.clone(),
-            str,
-            stream_builder: |set, str| {
-                Box::new(
-                    add_range(
-                        set.search(Str::from(str.as_ref()).starts_with()),
-                        ge,
-                        gt,
-                        le,
-                        lt,
+        with_state: bool,
+    ) -> PyResult<PyObject> {
+        if with_state {
+            let automaton =
+                AutomatonGraph::from_node(Node::StartsWith(Arc::new(Node::Str(str))));
+            let iter = AutomatonStateIteratorBuilder {
+                set: self.inner.clone(),
+                automaton: automaton.get(),
+                stream_builder: |set, automaton| {
+                    add_range(set.search(automaton.get()), ge, gt, le, lt).with_state()
+                },
+            }
+            .build();
+            Ok(iter.into_py(py))
+        } else {
+            let iter = KeyIteratorBuilder {
+                set: self.inner.clone(),
+                str,
+                stream_builder: |set, str| {
+                    Box::new(
+                        add_range(
+                            set.search(Str::from(str.as_ref()).starts_with()),
+                            ge,
+                            gt,
+                            le,
+                            lt,
+                        )
+                        .into_stream(),
                     )
-                    .into_stream(),
-                )
-            },
+                },
+            }
+            .build();
+            Ok(iter.into_py(py))
         }
-        .build()
     }
 
     /// Iterate over all keys that contain the subsequence str.
@@ -398,26 +526,45 @@ impl Set {
     /// gt (greater than),
     /// le (less than or equal),
     /// and lt (less than).
-    #[pyo3(signature = (str, ge=None, gt=None, le=None, lt=None))]
+    /// If with_state is True, yields (key, state) pairs instead of just key,
+    /// see search_with_state for what state means.
+    #[pyo3(signature = (str, ge=None, gt=None, le=None, lt=None, with_state=false))]
+    #[allow(clippy::too_many_arguments)]
     fn subsequence(
         &self,
+        py: Python<'_>,
         str: Vec<u8>,
         ge: Option<&[u8]>,
         gt: Option<&[u8]>,
         le: Option<&[u8]>,
         lt: Option<&[u8]>,
-    ) -> KeyIterator {
-        KeyIteratorBuilder {
-            set: self.inner.clone(),
-            str,
-            stream_builder: |set, str| {
-                Box::new(
-                    add_range(set.search(Subsequence::from(str.as_ref())), ge, gt, le, lt)
-                        .into_stream(),
-                )
-            },
+        with_state: bool,
+    ) -> PyResult<PyObject> {
+        if with_state {
+            let automaton = AutomatonGraph::from_node(Node::Subsequence(str));
+            let iter = AutomatonStateIteratorBuilder {
+                set: self.inner.clone(),
+                automaton: automaton.get(),
+                stream_builder: |set, automaton| {
+                    add_range(set.search(automaton.get()), ge, gt, le, lt).with_state()
+                },
+            }
+            .build();
+            Ok(iter.into_py(py))
+        } else {
+            let iter = KeyIteratorBuilder {
+                set: self.inner.clone(),
+                str,
+                stream_builder: |set, str| {
+                    Box::new(
+                        add_range(set.search(Subsequence::from(str.as_ref())), ge, gt, le, lt)
+                            .into_stream(),
+                    )
+                },
+            }
+            .build();
+            Ok(iter.into_py(py))
         }
-        .build()
     }
 
     /// Iterate over all keys that match the given Automaton.
@@ -445,67 +592,131 @@ impl Set {
         .build()
     }
 
+    /// Iterate over all (key, state) pairs whose key matches the given Automaton.
+    /// state is an opaque integer exposing the automaton's internal state at
+    /// the point of the match, or None where that isn't meaningful. E.g., for
+    /// a levenshtein automaton, state is the edit distance to the query, letting
+    /// callers rank fuzzy matches without recomputing the distance.
+    /// Optionally apply range limits
+    /// ge (greater than or equal),
+    /// gt (greater than),
+    /// le (less than or equal),
+    /// and lt (less than).
+    #[pyo3(signature = (automaton, ge=None, gt=None, le=None, lt=None))]
+    fn search_with_state(
+        &self,
+        automaton: &AutomatonGraph,
+        ge: Option<&[u8]>,
+        gt: Option<&[u8]>,
+        le: Option<&[u8]>,
+        lt: Option<&[u8]>,
+    ) -> AutomatonStateIterator {
+        AutomatonStateIteratorBuilder {
+            set: self.inner.clone(),
+            automaton: automaton.get(),
+            stream_builder: |set, automaton| {
+                add_range(set.search(automaton.get()), ge, gt, le, lt).with_state()
+            },
+        }
+        .build()
+    }
+
     /// Iterate over all keys with optional range limits
     /// ge (greater than or equal),
     /// gt (greater than),
     /// le (less than or equal),
     /// and lt (less than).
     /// If no limits are given this is equivalent to iter(self).
-    #[pyo3(signature = (ge=None, gt=None, le=None, lt=None))]
+    /// If with_state is True, yields (key, state) pairs instead of just key,
+    /// see search_with_state for what state means.
+    #[pyo3(signature = (ge=None, gt=None, le=None, lt=None, with_state=false))]
     fn range(
         &self,
+        py: Python<'_>,
         ge: Option<&[u8]>,
         gt: Option<&[u8]>,
         le: Option<&[u8]>,
         lt: Option<&[u8]>,
-    ) -> KeyIterator {
-        KeyIteratorBuilder {
-            set: self.inner.clone(),
-            str: Vec::new(),
-            stream_builder: |set, _| Box::new(add_range(set.range(), ge, gt, le, lt).into_stream()),
+        with_state: bool,
+    ) -> PyResult<PyObject> {
+        if with_state {
+            let automaton = AutomatonGraph::from_node(Node::AlwaysMatch);
+            let iter = AutomatonStateIteratorBuilder {
+                set: self.inner.clone(),
+                automaton: automaton.get(),
+                stream_builder: |set, automaton| {
+                    add_range(set.search(automaton.get()), ge, gt, le, lt).with_state()
+                },
+            }
+            .build();
+            Ok(iter.into_py(py))
+        } else {
+            let iter = KeyIteratorBuilder {
+                set: self.inner.clone(),
+                str: Vec::new(),
+                stream_builder: |set, _| {
+                    Box::new(add_range(set.range(), ge, gt, le, lt).into_stream())
+                },
+            }
+            .build();
+            Ok(iter.into_py(py))
         }
-        .build()
     }
 
     /// Build a new set that is the union of self and others.
     /// others must be instances of Set.
-    /// If path is ":memory:", returns a Buffer containing the set data
+    /// If path is ":memory:", returns a Set built directly from the result
     /// instead of writing to path.
     /// path can be str or Path.
     #[pyo3(signature = (path, *others))]
     #[allow(clippy::needless_pass_by_value)]
-    fn union(&self, path: PathBuf, others: &Bound<'_, PyTuple>) -> PyResult<Option<Buffer>> {
+    fn union(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        others: &Bound<'_, PyTuple>,
+    ) -> PyResult<PyObject> {
         let sets = setvec(self, others)?;
         let stream = opbuilder(&sets).union();
-        build_from_stream(&path, stream)
+        build_from_stream(py, &path, stream)
     }
 
     /// Build a new set that is the intersection of self and others.
     /// others must be instances of Set.
-    /// If path is ":memory:", returns a Buffer containing the set data
+    /// If path is ":memory:", returns a Set built directly from the result
     /// instead of writing to path.
     /// path can be str or Path.
     #[pyo3(signature = (path, *others))]
     #[allow(clippy::needless_pass_by_value)]
-    fn intersection(&self, path: PathBuf, others: &Bound<'_, PyTuple>) -> PyResult<Option<Buffer>> {
+    fn intersection(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        others: &Bound<'_, PyTuple>,
+    ) -> PyResult<PyObject> {
         let sets = setvec(self, others)?;
         let stream = opbuilder(&sets).intersection();
-        build_from_stream(&path, stream)
+        build_from_stream(py, &path, stream)
     }
 
     /// Build a new set that is the difference between self and all others,
     /// meaning the resulting set will contain all keys that are in self,
     /// but not in others.
     /// others must be instances of Set.
-    /// If path is ":memory:", returns a Buffer containing the set data
+    /// If path is ":memory:", returns a Set built directly from the result
     /// instead of writing to path.
     /// path can be str or Path.
     #[pyo3(signature = (path, *others))]
     #[allow(clippy::needless_pass_by_value)]
-    fn difference(&self, path: PathBuf, others: &Bound<'_, PyTuple>) -> PyResult<Option<Buffer>> {
+    fn difference(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        others: &Bound<'_, PyTuple>,
+    ) -> PyResult<PyObject> {
         let sets = setvec(self, others)?;
         let stream = opbuilder(&sets).difference();
-        build_from_stream(&path, stream)
+        build_from_stream(py, &path, stream)
     }
 
     /// Build a new set that is the symmetric difference between self and others.
@@ -513,18 +724,159 @@ impl Set {
     /// if only one other set is given, it will contain all keys that are in either
     /// self or others, but not in both.
     /// others must be instances of Set.
-    /// If path is ":memory:", returns a Buffer containing the set data
+    /// If path is ":memory:", returns a Set built directly from the result
     /// instead of writing to path.
     /// path can be str or Path.
     #[pyo3(signature = (path, *others))]
     #[allow(clippy::needless_pass_by_value)]
     fn symmetric_difference(
         &self,
+        py: Python<'_>,
         path: PathBuf,
         others: &Bound<'_, PyTuple>,
-    ) -> PyResult<Option<Buffer>> {
+    ) -> PyResult<PyObject> {
         let sets = setvec(self, others)?;
         let stream = opbuilder(&sets).symmetric_difference();
-        build_from_stream(&path, stream)
+        build_from_stream(py, &path, stream)
+    }
+
+    /// Returns the union of self and other as a new, in-memory Set.
+    /// other must be Set. Equivalent to self.union(":memory:", other).
+    fn __or__(&self, other: &Set) -> PyResult<Set> {
+        let sets = vec![self.inner.clone(), other.inner.clone()];
+        set_from_stream(opbuilder(&sets).union())
+    }
+
+    /// Returns the intersection of self and other as a new, in-memory Set.
+    /// other must be Set. Equivalent to self.intersection(":memory:", other).
+    fn __and__(&self, other: &Set) -> PyResult<Set> {
+        let sets = vec![self.inner.clone(), other.inner.clone()];
+        set_from_stream(opbuilder(&sets).intersection())
+    }
+
+    /// Returns the difference between self and other as a new, in-memory Set.
+    /// other must be Set. Equivalent to self.difference(":memory:", other).
+    fn __sub__(&self, other: &Set) -> PyResult<Set> {
+        let sets = vec![self.inner.clone(), other.inner.clone()];
+        set_from_stream(opbuilder(&sets).difference())
+    }
+
+    /// Returns the symmetric difference between self and other as a new,
+    /// in-memory Set. other must be Set. Equivalent to
+    /// self.symmetric_difference(":memory:", other).
+    fn __xor__(&self, other: &Set) -> PyResult<Set> {
+        let sets = vec![self.inner.clone(), other.inner.clone()];
+        set_from_stream(opbuilder(&sets).symmetric_difference())
+    }
+
+    /// Build a new set containing the keys present in at least threshold of
+    /// self and others, i.e., a threshold/quorum merge of
+    /// N = len(others) + 1 sets. threshold=N is equivalent to intersection,
+    /// threshold=1 is equivalent to union.
+    /// others must be instances of Set.
+    /// If path is ":memory:", returns a Set built directly from the result
+    /// instead of writing to path.
+    /// path can be str or Path.
+    #[pyo3(signature = (path, *others, threshold))]
+    fn quorum(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        others: &Bound<'_, PyTuple>,
+        threshold: usize,
+    ) -> PyResult<PyObject> {
+        let sets = setvec(self, others)?;
+        build_quorum(py, &path, &sets, threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_of(keys: &[&[u8]]) -> Arc<PySet> {
+        let mut builder = SetBuilder::new(Vec::new()).unwrap();
+        for key in keys {
+            builder.insert(key).unwrap();
+        }
+        let buf = builder.into_inner().unwrap();
+        Arc::new(fst::Set::new(ByteSource::Owned(buf)).unwrap())
+    }
+
+    fn quorum_keys(sets: &[Arc<PySet>], threshold: usize) -> Vec<Vec<u8>> {
+        let buf = fill_quorum(sets, threshold, Vec::new()).unwrap();
+        let set = fst::Set::new(buf).unwrap();
+        let mut stream = set.stream();
+        let mut keys = Vec::new();
+        while let Some(key) = stream.next() {
+            keys.push(key.to_vec());
+        }
+        keys
+    }
+
+    fn sets() -> Vec<Arc<PySet>> {
+        vec![
+            set_of(&[b"a", b"b", b"c"]),
+            set_of(&[b"b", b"c", b"d"]),
+            set_of(&[b"c", b"d", b"e"]),
+        ]
+    }
+
+    #[test]
+    fn quorum_threshold_zero_is_same_as_union() {
+        // Every key present in the merged stream appears in at least one set,
+        // so threshold=0 can't surface any keys beyond the union.
+        assert_eq!(quorum_keys(&sets(), 0), quorum_keys(&sets(), 1));
+    }
+
+    #[test]
+    fn quorum_threshold_one_is_union() {
+        assert_eq!(
+            quorum_keys(&sets(), 1),
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec(), b"e".to_vec()]
+        );
+    }
+
+    #[test]
+    fn quorum_threshold_n_is_intersection() {
+        assert_eq!(quorum_keys(&sets(), 3), vec![b"c".to_vec()]);
+    }
+
+    #[test]
+    fn quorum_threshold_above_n_matches_nothing() {
+        assert_eq!(quorum_keys(&sets(), 4), Vec::<Vec<u8>>::new());
+    }
+
+    fn keys_of(set: &Set) -> Vec<Vec<u8>> {
+        let mut stream = set.inner.stream();
+        let mut keys = Vec::new();
+        while let Some(key) = stream.next() {
+            keys.push(key.to_vec());
+        }
+        keys
+    }
+
+    #[test]
+    fn dunder_operators_return_a_live_set_built_via_set_from_stream() {
+        let a = Set {
+            inner: set_of(&[b"a", b"b", b"c"]),
+        };
+        let b = Set {
+            inner: set_of(&[b"b", b"c", b"d"]),
+        };
+
+        assert_eq!(
+            keys_of(&a.__or__(&b).unwrap()),
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec()]
+        );
+        assert_eq!(
+            keys_of(&a.__and__(&b).unwrap()),
+            vec![b"b".to_vec(), b"c".to_vec()]
+        );
+        assert_eq!(keys_of(&a.__sub__(&b).unwrap()), vec![b"a".to_vec()]);
+        assert_eq!(
+            keys_of(&a.__xor__(&b).unwrap()),
+            vec![b"a".to_vec(), b"d".to_vec()]
+        );
     }
 }